@@ -0,0 +1,25 @@
+//! Error and result types.
+
+use thiserror::Error;
+
+/// A specialized [`std::result::Result`] for this crate's fallible operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The error type returned by most of this crate's fallible operations.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("unsupported scheme: {}", .0.as_deref().unwrap_or("<none>"))]
+    UnsupportedScheme(Option<String>),
+
+    #[error("invalid client parameters: {0}")]
+    InvalidParams(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("network error: {0}")]
+    Network(#[from] hyper::Error),
+
+    /// Returned when [`crate::Client::with_timeout`] elapses before a
+    /// response arrives.
+    #[error("request timed out")]
+    Timeout,
+}
@@ -0,0 +1,164 @@
+//! Proxy configuration for [`crate::Client`].
+//!
+//! Honors an explicit [`crate::Client::with_proxy`] override as well as the
+//! standard `HTTP_PROXY`/`HTTPS_PROXY`/`NO_PROXY` environment variables
+//! (and their lowercase spellings) that `Client::default()` picks up.
+//!
+//! Ambient environment state must never crash a zero-argument
+//! `Client::default()`: an unparsable `HTTP_PROXY`/`HTTPS_PROXY` value is
+//! logged and that scheme is simply left unproxied, rather than panicking.
+//! An explicit, user-typed [`crate::Client::with_proxy`] value is held to a
+//! stricter standard: a malformed URL there is a caller mistake, not ambient
+//! state, so it's surfaced as an [`crate::error::Error`] instead.
+
+use hyper::client::connect::Connect;
+use hyper_proxy::{Custom, Intercept, Proxy, ProxyConnector};
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Default)]
+pub(crate) struct ProxyConfig {
+    http: Option<hyper::Uri>,
+    https: Option<hyper::Uri>,
+    no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn from_env() -> Self {
+        Self {
+            http: parse_proxy_env(&["HTTP_PROXY", "http_proxy"]),
+            https: parse_proxy_env(&["HTTPS_PROXY", "https_proxy"]),
+            no_proxy: env_var(&["NO_PROXY", "no_proxy"]),
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: String) -> Result<Self> {
+        let uri: hyper::Uri = proxy
+            .trim()
+            .parse()
+            .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+        self.http = Some(uri.clone());
+        self.https = Some(uri);
+        Ok(self)
+    }
+}
+
+fn env_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| std::env::var(name).ok())
+        .filter(|value| !value.is_empty())
+}
+
+/// Parses an env var's proxy URL, logging and discarding it instead of
+/// panicking if it's malformed — this runs from `Client::default()`, so
+/// ambient, user-uncontrolled environment state must not be able to crash it.
+fn parse_proxy_env(names: &[&str]) -> Option<hyper::Uri> {
+    let value = env_var(names)?;
+    match value.trim().parse() {
+        Ok(uri) => Some(uri),
+        Err(err) => {
+            log::warn!(
+                "ignoring invalid proxy URL from {} ({value:?}): {err}",
+                names[0]
+            );
+            None
+        }
+    }
+}
+
+fn is_excluded(no_proxy: Option<&str>, host: &str) -> bool {
+    let Some(no_proxy) = no_proxy else {
+        return false;
+    };
+    no_proxy.split(',').any(|pattern| {
+        // `.example.com` is the common convention for "this domain and all
+        // of its subdomains"; without stripping it, `host.ends_with(".{pattern}")`
+        // would require a literal double dot and never match.
+        let pattern = pattern.trim().trim_start_matches('.');
+        !pattern.is_empty() && (host == pattern || host.ends_with(&format!(".{pattern}")))
+    })
+}
+
+/// Wraps `connector` in a [`ProxyConnector`] configured for `scheme`
+/// (`"http"` or `"https"`), honoring `config`'s proxy and `NO_PROXY` list.
+/// With no proxy configured for `scheme`, the returned connector behaves
+/// exactly like `connector`.
+pub(crate) fn wrap<C>(connector: C, scheme: &'static str, config: &ProxyConfig) -> ProxyConnector<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let mut proxy_connector =
+        ProxyConnector::new(connector).expect("failed to build proxy connector");
+
+    let target = match scheme {
+        "http" => config.http.clone(),
+        "https" => config.https.clone(),
+        _ => None,
+    };
+
+    if let Some(uri) = target {
+        let no_proxy = config.no_proxy.clone();
+        let intercept = Intercept::Custom(Custom::from(
+            move |_scheme: &str, host: &str, _port: Option<u16>| {
+                !is_excluded(no_proxy.as_deref(), host)
+            },
+        ));
+        proxy_connector.add_proxy(Proxy::new(intercept, uri));
+    }
+
+    proxy_connector
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_excluded_matches_exact_host() {
+        assert!(is_excluded(Some("example.com"), "example.com"));
+        assert!(!is_excluded(Some("example.com"), "other.com"));
+    }
+
+    #[test]
+    fn is_excluded_matches_subdomains() {
+        assert!(is_excluded(Some("example.com"), "api.example.com"));
+        assert!(!is_excluded(Some("example.com"), "notexample.com"));
+    }
+
+    #[test]
+    fn is_excluded_handles_leading_dot_convention() {
+        assert!(is_excluded(Some(".example.com"), "api.example.com"));
+        assert!(is_excluded(Some(".example.com"), "example.com"));
+    }
+
+    #[test]
+    fn is_excluded_checks_every_entry_in_the_list() {
+        assert!(is_excluded(Some("foo.com, .example.com ,bar.com"), "api.example.com"));
+        assert!(!is_excluded(Some("foo.com,bar.com"), "api.example.com"));
+    }
+
+    #[test]
+    fn is_excluded_with_no_list_excludes_nothing() {
+        assert!(!is_excluded(None, "example.com"));
+    }
+
+    #[test]
+    fn malformed_env_proxy_url_is_ignored_not_panicking() {
+        assert!(parse_proxy_env(&["DOES_NOT_EXIST_AS_AN_ENV_VAR"]).is_none());
+
+        std::env::set_var("CLICKHOUSE_RS_TEST_MALFORMED_PROXY", "not a valid uri");
+        assert!(parse_proxy_env(&["CLICKHOUSE_RS_TEST_MALFORMED_PROXY"]).is_none());
+    }
+
+    #[test]
+    fn with_proxy_rejects_malformed_url_as_an_error() {
+        assert!(matches!(
+            ProxyConfig::default().with_proxy("not a valid uri".to_owned()),
+            Err(Error::InvalidParams(_))
+        ));
+        assert!(ProxyConfig::default()
+            .with_proxy("http://proxy.example.com:8080".to_owned())
+            .is_ok());
+    }
+}
@@ -0,0 +1,266 @@
+//! Helpers for building the HTTPS connectors backing [`crate::Client`].
+//!
+//! Two independent backends can be compiled in: `native-tls` (via `hyper-tls`,
+//! gated by the `tls` feature) and `rustls` (via `hyper-rustls`, gated by the
+//! `rustls-tls` feature). When both are enabled, [`TlsBackend`] selects which
+//! one `Client::request()` uses for the `https` scheme at runtime.
+//!
+//! The `rustls-tls` feature has sub-features for the crypto provider
+//! (`rustls-tls-ring` or `rustls-tls-aws-lc-rs`) and the root certificate
+//! source (`rustls-tls-webpki-roots` or `rustls-tls-native-roots`).
+//!
+//! Building a connector can fail on bad caller-supplied input (an invalid
+//! certificate/identity, or a PKCS#12 identity under the `rustls-tls`
+//! backend, which only supports PEM), so [`native_tls_connector`] and
+//! [`rustls_connector`] return [`crate::error::Result`] rather than
+//! panicking.
+
+use hyper::client::connect::HttpConnector;
+
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+use crate::error::{Error, Result};
+
+#[cfg(feature = "tls")]
+pub(crate) type NativeTlsConnector = hyper_tls::HttpsConnector<HttpConnector>;
+
+#[cfg(feature = "rustls-tls")]
+pub(crate) type RustlsConnector = hyper_rustls::HttpsConnector<HttpConnector>;
+
+/// Selects which compiled-in TLS implementation [`crate::Client`] uses for
+/// the `https` scheme.
+///
+/// Only meaningful when both the `tls` and `rustls-tls` features are
+/// enabled; with a single backend compiled in, it's used unconditionally.
+#[cfg(all(feature = "tls", feature = "rustls-tls"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tls", feature = "rustls-tls"))))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsBackend {
+    NativeTls,
+    Rustls,
+}
+
+#[cfg(all(feature = "tls", feature = "rustls-tls"))]
+impl Default for TlsBackend {
+    fn default() -> Self {
+        // Keep the behavior `Client::default()` had before `rustls-tls` existed.
+        Self::NativeTls
+    }
+}
+
+/// A PEM- or DER-encoded certificate to trust as a root CA, in addition to
+/// whatever root store the compiled-in TLS backend uses by default.
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls-tls"))))]
+#[derive(Clone)]
+pub struct Certificate(CertificateData);
+
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+#[derive(Clone)]
+enum CertificateData {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+}
+
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+impl Certificate {
+    pub fn from_pem(pem: impl Into<Vec<u8>>) -> Self {
+        Self(CertificateData::Pem(pem.into()))
+    }
+
+    pub fn from_der(der: impl Into<Vec<u8>>) -> Self {
+        Self(CertificateData::Der(der.into()))
+    }
+}
+
+/// A client certificate and private key presented during the TLS handshake,
+/// for servers that require mutual TLS.
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls-tls"))))]
+#[derive(Clone)]
+pub struct Identity(IdentityData);
+
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+#[derive(Clone)]
+enum IdentityData {
+    Pem { cert: Vec<u8>, key: Vec<u8> },
+    Pkcs12 { der: Vec<u8>, password: String },
+}
+
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+impl Identity {
+    /// Builds an identity from a PEM-encoded certificate chain and private key.
+    pub fn from_pem(cert: impl Into<Vec<u8>>, key: impl Into<Vec<u8>>) -> Self {
+        Self(IdentityData::Pem {
+            cert: cert.into(),
+            key: key.into(),
+        })
+    }
+
+    /// Builds an identity from a password-protected PKCS#12 archive.
+    pub fn from_pkcs12_der(der: impl Into<Vec<u8>>, password: impl Into<String>) -> Self {
+        Self(IdentityData::Pkcs12 {
+            der: der.into(),
+            password: password.into(),
+        })
+    }
+}
+
+/// The trust configuration shared between whichever TLS backend(s) are
+/// compiled in: extra root CAs and an optional client identity for mTLS.
+#[cfg(any(feature = "tls", feature = "rustls-tls"))]
+#[derive(Clone, Default)]
+pub(crate) struct TlsConfig {
+    pub ca_certificates: Vec<Certificate>,
+    pub identity: Option<Identity>,
+}
+
+#[cfg(feature = "tls")]
+pub(crate) fn native_tls_connector(
+    http: HttpConnector,
+    config: &TlsConfig,
+) -> Result<NativeTlsConnector> {
+    let mut builder = native_tls::TlsConnector::builder();
+
+    for ca in &config.ca_certificates {
+        let cert = match &ca.0 {
+            CertificateData::Pem(pem) => native_tls::Certificate::from_pem(pem),
+            CertificateData::Der(der) => native_tls::Certificate::from_der(der),
+        }
+        .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+        builder.add_root_certificate(cert);
+    }
+
+    if let Some(identity) = &config.identity {
+        let identity = match &identity.0 {
+            IdentityData::Pem { cert, key } => native_tls::Identity::from_pkcs8(cert, key),
+            IdentityData::Pkcs12 { der, password } => {
+                native_tls::Identity::from_pkcs12(der, password)
+            }
+        }
+        .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+        builder.identity(identity);
+    }
+
+    let connector = builder
+        .build()
+        .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+    Ok(hyper_tls::HttpsConnector::from((http, connector.into())))
+}
+
+/// Installs the process-default `rustls` crypto provider selected by the
+/// `rustls-tls-ring`/`rustls-tls-aws-lc-rs` feature, if one hasn't been
+/// installed already (e.g. by another dependency). Without this, building a
+/// `rustls::ClientConfig` panics at runtime when zero or more than one
+/// provider is linked in.
+#[cfg(feature = "rustls-tls-ring")]
+fn ensure_crypto_provider() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+}
+
+#[cfg(all(feature = "rustls-tls-aws-lc-rs", not(feature = "rustls-tls-ring")))]
+fn ensure_crypto_provider() {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+}
+
+#[cfg(all(
+    feature = "rustls-tls",
+    not(any(feature = "rustls-tls-ring", feature = "rustls-tls-aws-lc-rs"))
+))]
+fn ensure_crypto_provider() {
+    // Neither provider sub-feature is selected; fall back to whatever the
+    // dependency graph installed as the process default, if anything.
+}
+
+#[cfg(feature = "rustls-tls")]
+pub(crate) fn rustls_connector(http: HttpConnector, config: &TlsConfig) -> Result<RustlsConnector> {
+    ensure_crypto_provider();
+
+    if config.ca_certificates.is_empty() && config.identity.is_none() {
+        let builder = hyper_rustls::HttpsConnectorBuilder::new();
+
+        #[cfg(feature = "rustls-tls-webpki-roots")]
+        let builder = builder.with_webpki_roots();
+        #[cfg(all(
+            feature = "rustls-tls-native-roots",
+            not(feature = "rustls-tls-webpki-roots")
+        ))]
+        let builder = builder
+            .with_native_roots()
+            .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+
+        return Ok(builder.https_or_http().enable_http1().wrap_connector(http));
+    }
+
+    let mut roots = rustls::RootCertStore::empty();
+    #[cfg(feature = "rustls-tls-webpki-roots")]
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    // Mirror the empty-config branch above: with no `ca_certificates`/
+    // `identity`, `with_native_roots()` seeds this backend's default trust
+    // store from the OS. Once any CA/identity is set, the root store is
+    // instead built up by hand here, so native roots must be loaded the
+    // same way here too — otherwise this branch is the only one with a
+    // trust store, and every ordinary, OS-trusted HTTPS server fails
+    // validation the moment a caller sets any CA cert or identity.
+    #[cfg(all(
+        feature = "rustls-tls-native-roots",
+        not(feature = "rustls-tls-webpki-roots")
+    ))]
+    roots.extend(
+        rustls_native_certs::load_native_certs()
+            .map_err(|err| Error::InvalidParams(Box::new(err)))?,
+    );
+
+    for ca in &config.ca_certificates {
+        match &ca.0 {
+            CertificateData::Der(der) => {
+                roots
+                    .add(der.clone().into())
+                    .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+            }
+            // A PEM blob may bundle more than one certificate (e.g. a CA
+            // chain); register every certificate it contains, not just the
+            // first.
+            CertificateData::Pem(pem) => {
+                let mut certs = rustls_pemfile::certs(&mut &pem[..]).peekable();
+                if certs.peek().is_none() {
+                    return Err(Error::InvalidParams("PEM input has no certificate".into()));
+                }
+                for cert in certs {
+                    let cert = cert.map_err(|err| Error::InvalidParams(Box::new(err)))?;
+                    roots
+                        .add(cert)
+                        .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+                }
+            }
+        }
+    }
+
+    let tls_config = rustls::ClientConfig::builder().with_root_certificates(roots);
+
+    let tls_config = match &config.identity {
+        Some(Identity(IdentityData::Pem { cert, key })) => {
+            let certs = rustls_pemfile::certs(&mut &cert[..])
+                .map(|cert| cert.map(|cert| cert.into_owned().into()))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|err| Error::InvalidParams(Box::new(err)))?;
+            let key = rustls_pemfile::private_key(&mut &key[..])
+                .map_err(|err| Error::InvalidParams(Box::new(err)))?
+                .ok_or_else(|| Error::InvalidParams("PEM input has no private key".into()))?;
+            tls_config
+                .with_client_auth_cert(certs, key)
+                .map_err(|err| Error::InvalidParams(Box::new(err)))?
+        }
+        Some(Identity(IdentityData::Pkcs12 { .. })) => {
+            return Err(Error::InvalidParams(
+                "PKCS#12 identities are only supported with the `tls` (native-tls) backend".into(),
+            ))
+        }
+        None => tls_config.with_no_client_auth(),
+    };
+
+    Ok(hyper_rustls::HttpsConnectorBuilder::new()
+        .with_tls_config(tls_config)
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(http))
+}